@@ -0,0 +1,151 @@
+//! Source-located diagnostics for USX parse warnings.
+//!
+//! Each [`Diagnostic`] carries one or more byte-offset [`Span`]s into the
+//! original document text. [`render`] turns those spans into compiler-style
+//! caret output (`^^^` under primary spans, `---` under secondary ones) the
+//! way a compiler reports an error against a source file; `--json` mode
+//! also serializes the same [`Diagnostic`] structs into its summary (the
+//! rendered form still goes to stderr unless `-quiet` is given).
+
+use serde::Serialize;
+
+/// A byte-offset range into the source text.
+#[derive(Clone, Copy, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// One labeled span within a diagnostic: primary spans are underlined with
+/// `^^^`, secondary ones with `---`.
+#[derive(Clone, Serialize)]
+pub struct Label {
+    pub span: Span,
+    pub text: String,
+    pub primary: bool,
+}
+
+/// A single parse warning, with the span(s) that explain it.
+#[derive(Clone, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub labels: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(message: &str) -> Diagnostic {
+        Diagnostic {
+            message: message.to_string(),
+            labels: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_label(mut self, span: Span, text: &str, primary: bool) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            text: text.to_string(),
+            primary,
+        });
+        self
+    }
+}
+
+/// Maps byte offsets to 0-based `(line, column)` against a precomputed
+/// table of line-start offsets, the same way a compiler's source map does.
+struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> LineIndex {
+        let mut line_starts = vec![0];
+        for (i, b) in source.bytes().enumerate() {
+            if b == b'\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        LineIndex { line_starts }
+    }
+
+    fn locate(&self, pos: usize) -> (usize, usize) {
+        let line = match self.line_starts.binary_search(&pos) {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+        (line, pos.saturating_sub(self.line_starts[line]))
+    }
+
+    fn line_text<'a>(&self, source: &'a str, line: usize) -> &'a str {
+        let start = self.line_starts[line];
+        let end = self.line_starts.get(line + 1).map(|e| e.saturating_sub(1)).unwrap_or(source.len());
+        &source[start..end.max(start).min(source.len())]
+    }
+}
+
+/// Render one diagnostic against `source`: the message, then each labeled
+/// line with a caret (`^^^`) or dash (`---`) run under the span and the
+/// label text beside it.
+pub fn render(source: &str, diagnostic: &Diagnostic) -> String {
+    let index = LineIndex::new(source);
+    let mut out = format!("warning: {}\n", diagnostic.message);
+
+    for label in &diagnostic.labels {
+        let (line, byte_col) = index.locate(label.span.start);
+        let (end_line, end_byte_col) = index.locate(label.span.end.max(label.span.start));
+
+        let text = index.line_text(source, line);
+        let col = text.get(..byte_col).map(|s| s.chars().count()).unwrap_or(byte_col);
+        let end_col = if end_line == line {
+            text.get(..end_byte_col).map(|s| s.chars().count()).unwrap_or(end_byte_col)
+        } else {
+            col
+        };
+        let width = end_col.saturating_sub(col).max(1);
+
+        let marker = if label.primary { '^' } else { '-' };
+        out.push_str(&format!("  --> line {}, column {}\n", line + 1, col + 1));
+        out.push_str(&format!("   | {}\n", text));
+        out.push_str(&format!("   | {}{} {}\n", " ".repeat(col), marker.to_string().repeat(width), label.text));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_counts_bytes_within_the_line() {
+        let source = "\u{201c}hello\u{201d} <note>";
+        let index = LineIndex::new(source);
+        let byte_offset = source.find("<note").unwrap();
+        let (line, byte_col) = index.locate(byte_offset);
+        assert_eq!(line, 0);
+        assert_eq!(byte_col, byte_offset);
+        assert_eq!(index.line_text(source, 0), source);
+    }
+
+    #[test]
+    fn render_aligns_caret_by_char_not_byte_on_multibyte_source() {
+        let source = "\u{201c}hello\u{201d} <note>";
+        let byte_start = source.find("<note").unwrap();
+        let byte_end = byte_start + "<note".len();
+        let diagnostic = Diagnostic::new("test").with_label(
+            Span {
+                start: byte_start,
+                end: byte_end,
+            },
+            "here",
+            true,
+        );
+
+        let out = render(source, &diagnostic);
+
+        // "\u{201c}hello\u{201d} " is 8 *characters* wide even though it's
+        // 12 *bytes*; the caret line must pad/underline by character count.
+        let expected_caret_line = format!("   | {}{}", " ".repeat(8), "^".repeat(5));
+        assert!(out.contains("column 9"));
+        assert!(out.lines().any(|line| line == expected_caret_line + " here"));
+    }
+}
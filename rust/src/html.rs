@@ -0,0 +1,155 @@
+//! HTML export: render a parsed [`crate::Document`] as a browsable,
+//! chapter-per-file HTML export.
+//!
+//! Unlike the row-table writers in [`crate::writers`], this renders the
+//! verse text as markup again rather than treating each row as an opaque
+//! string: styled runs use the same tags `get_styled_tag_name` maps USX/USFM
+//! styles onto, footnotes/crossrefs become `<sup>` anchors into a per-chapter
+//! notes section, and subtitles become headings.
+
+use crate::{split_notes, Row};
+use regex::Regex;
+
+/// One rendered chapter, ready to write to its own `.html` file.
+pub struct ChapterHtml {
+    pub chapter: String,
+    pub html: String,
+}
+
+/// Render every chapter in `rows` (already sorted book/chapter/verse) into a
+/// standalone HTML document per chapter.
+pub fn render_chapters(book: &str, rows: &[Row]) -> Vec<ChapterHtml> {
+    let mut chapters: Vec<ChapterHtml> = Vec::new();
+    let mut current_chapter = String::new();
+    let mut body = String::new();
+    let mut notes: Vec<(String, String)> = Vec::new();
+    let mut last_subtitle = String::new();
+
+    for row in rows {
+        if row.chapter != current_chapter {
+            if !current_chapter.is_empty() {
+                chapters.push(finish_chapter(book, &current_chapter, &body, &notes));
+            }
+            current_chapter = row.chapter.clone();
+            body.clear();
+            notes.clear();
+            last_subtitle.clear();
+            body.push_str(&format!("<h1>{} {}</h1>\n", escape_html(book), escape_html(&current_chapter)));
+        }
+
+        if !row.subtitle.is_empty() && row.subtitle != last_subtitle {
+            body.push_str(&format!("<h2>{}</h2>\n", escape_html(&row.subtitle)));
+            last_subtitle = row.subtitle.clone();
+        }
+
+        body.push_str("<p class=\"verse\">");
+        body.push_str(&format!("<sup class=\"verse-num\">{}</sup> ", escape_html(&row.verse)));
+        body.push_str(&render_styled(&row.text_styled));
+
+        for note in split_notes(&row.footnotes) {
+            push_note_anchor(&mut body, &mut notes, &current_chapter, &row.verse, "fn", &note);
+        }
+        for xref in split_notes(&row.crossrefs) {
+            push_note_anchor(&mut body, &mut notes, &current_chapter, &row.verse, "xr", &xref);
+        }
+
+        body.push_str("</p>\n");
+    }
+
+    if !current_chapter.is_empty() {
+        chapters.push(finish_chapter(book, &current_chapter, &body, &notes));
+    }
+
+    chapters
+}
+
+fn finish_chapter(book: &str, chapter: &str, body: &str, notes: &[(String, String)]) -> ChapterHtml {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n");
+    html.push_str(&format!("<meta charset=\"utf-8\">\n<title>{} {}</title>\n", escape_html(book), escape_html(chapter)));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(body);
+
+    if !notes.is_empty() {
+        html.push_str("<hr>\n<section class=\"notes\">\n<ol>\n");
+        for (id, text) in notes {
+            html.push_str(&format!("<li id=\"note-{0}\"><a href=\"#ref-{0}\">^</a> {1}</li>\n", id, escape_html(text)));
+        }
+        html.push_str("</ol>\n</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    ChapterHtml {
+        chapter: chapter.to_string(),
+        html,
+    }
+}
+
+fn push_note_anchor(body: &mut String, notes: &mut Vec<(String, String)>, chapter: &str, verse: &str, kind: &str, text: &str) {
+    let idx = notes.len() + 1;
+    let id = format!("{}-{}-{}-{}", kind, chapter, verse, idx);
+    body.push_str(&format!(" <sup class=\"{0}\"><a href=\"#note-{1}\" id=\"ref-{1}\">{2}</a></sup>", kind, id, idx));
+    notes.push((id, text.to_string()));
+}
+
+/// Escape text for safe placement between HTML tags.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Escape a `text_styled` string for HTML while leaving the style tags it
+/// already carries (`<i>`, `<b>`, ...) untouched.
+fn render_styled(styled: &str) -> String {
+    let tag_pattern = Regex::new(r"</?(?:wj|add|nd|bdit|i|b|span)>").unwrap();
+
+    let mut out = String::new();
+    let mut last = 0;
+    for m in tag_pattern.find_iter(styled) {
+        out.push_str(&escape_html(&styled[last..m.start()]));
+        out.push_str(m.as_str());
+        last = m.end();
+    }
+    out.push_str(&escape_html(&styled[last..]));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_html_escapes_reserved_chars() {
+        assert_eq!(escape_html(r#"<tag> & "quote""#), "&lt;tag&gt; &amp; &quot;quote&quot;");
+    }
+
+    #[test]
+    fn render_styled_escapes_text_but_preserves_known_tags() {
+        let out = render_styled(r#"She said "hi" <b>& bye</b>"#);
+        assert_eq!(out, r#"She said &quot;hi&quot; <b>&amp; bye</b>"#);
+    }
+
+    #[test]
+    fn render_chapters_escapes_verse_text_and_keeps_style_tags() {
+        let rows = vec![Row {
+            book: "GEN".to_string(),
+            chapter: "1".to_string(),
+            verse: "1".to_string(),
+            text_plain: "Let there be light.".to_string(),
+            text_styled: r#"Let there be <i>light</i> & "dark"."#.to_string(),
+            footnotes: "A & B note.".to_string(),
+            crossrefs: String::new(),
+            subtitle: "Creation".to_string(),
+            book_name: "Genesis".to_string(),
+        }];
+
+        let chapters = render_chapters("GEN", &rows);
+        assert_eq!(chapters.len(), 1);
+        let html = &chapters[0].html;
+
+        assert!(html.contains("<h1>GEN 1</h1>"));
+        assert!(html.contains("<h2>Creation</h2>"));
+        assert!(html.contains(r#"Let there be <i>light</i> &amp; &quot;dark&quot;."#));
+        assert!(html.contains(r#"<sup class="fn"><a href="#note-fn-1-1-1" id="ref-fn-1-1-1">1</a></sup>"#));
+        assert!(html.contains("A &amp; B note."));
+    }
+}
@@ -0,0 +1,598 @@
+//! Parsing core for USX/USFM Scripture markup.
+//!
+//! This crate is the reusable half of the converter: it turns USX or USFM
+//! source text into a structured [`Document`] of [`Row`]s, with no file I/O
+//! or CLI concerns. The `usxtocsv` binary is a thin wrapper around
+//! [`parse_usx`]/[`parse_usfm`] that adds file discovery and writers.
+//!
+//! Both formats are parsed down onto the same `Node` tree (elements named
+//! `book`/`chapter`/`verse`/`para`/`char`/`note`, matching USX's own
+//! vocabulary) and then walked by a single `process_document` pass, so
+//! style mapping and note extraction only need to live in one place.
+
+pub mod diagnostics;
+pub mod html;
+pub mod locale;
+pub mod markdown;
+pub mod reverse;
+mod usfm;
+pub mod writers;
+
+use diagnostics::{Diagnostic, Span};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum NodeType {
+    Element,
+    Text,
+}
+
+#[derive(Clone)]
+pub(crate) struct Node {
+    pub(crate) node_type: NodeType,
+    pub(crate) name: String,
+    pub(crate) attrs: HashMap<String, String>,
+    pub(crate) children: Vec<Node>,
+    pub(crate) text: String,
+    /// Byte offsets into the source this node was parsed from. USX nodes
+    /// carry real offsets; USFM builds synthetic nodes with no source
+    /// bytes to point at, so they're left at `(0, 0)`.
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+impl Node {
+    pub(crate) fn element(name: &str) -> Node {
+        Node {
+            node_type: NodeType::Element,
+            name: name.to_string(),
+            attrs: HashMap::new(),
+            children: Vec::new(),
+            text: String::new(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub(crate) fn text(text: &str) -> Node {
+        Node {
+            node_type: NodeType::Text,
+            name: String::new(),
+            attrs: HashMap::new(),
+            children: Vec::new(),
+            text: text.to_string(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub(crate) fn with_attr(mut self, name: &str, value: &str) -> Node {
+        self.attrs.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub(crate) fn with_children(mut self, children: Vec<Node>) -> Node {
+        self.children = children;
+        self
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// One verse-level record produced by either parser.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Row {
+    pub book: String,
+    pub chapter: String,
+    pub verse: String,
+    pub text_plain: String,
+    pub text_styled: String,
+    pub footnotes: String,
+    pub crossrefs: String,
+    pub subtitle: String,
+    /// The localized book name, filled in by [`crate::locale::localize_rows`].
+    /// Equal to `book` until localization is applied.
+    #[serde(default)]
+    pub book_name: String,
+}
+
+/// Join note texts for the `footnotes`/`crossrefs` cell, escaping any
+/// literal `|` in a note's own text so a multi-note split on `" | "`
+/// doesn't fragment on it.
+pub(crate) fn join_notes(notes: &[String]) -> String {
+    notes.iter().map(|n| n.replace('|', "\\|")).collect::<Vec<_>>().join(" | ")
+}
+
+/// Split a `footnotes`/`crossrefs` cell back into individual note texts,
+/// undoing [`join_notes`]'s escaping.
+pub(crate) fn split_notes(joined: &str) -> Vec<String> {
+    if joined.is_empty() {
+        Vec::new()
+    } else {
+        joined.split(" | ").map(|s| s.replace("\\|", "|")).collect()
+    }
+}
+
+/// The parsed, structured result of converting one USX or USFM source: the
+/// book code plus every verse row, sorted in canonical reading order, plus
+/// any non-fatal [`Diagnostic`]s raised while walking the source.
+pub struct Document {
+    pub book: String,
+    pub rows: Vec<Row>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+struct DocState {
+    book_code: String,
+    current_chapter: String,
+    current_verse: String,
+    current_plain: String,
+    current_styled: String,
+    current_footnotes: Vec<String>,
+    current_crossrefs: Vec<String>,
+    current_subtitle: String,
+    rows: Vec<Row>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Parse a USX document's text into a [`Document`].
+pub fn parse_usx(xml: &str) -> Result<Document, String> {
+    let (root, xml_diagnostics) = parse_xml_str(xml)?;
+    if root.name != "usx" {
+        return Err("No <usx> root found".to_string());
+    }
+    let mut document = process_document(&root)?;
+    document.diagnostics.splice(0..0, xml_diagnostics);
+    Ok(document)
+}
+
+/// Parse a USFM/SFM document's text into a [`Document`].
+///
+/// `fallback_book` is used as the book code when the source has no `\id`
+/// line (callers typically pass the file stem for this).
+pub fn parse_usfm(text: &str, fallback_book: &str) -> Result<Document, String> {
+    let root = usfm::build_node_tree(text, fallback_book);
+    process_document(&root)
+}
+
+/// Walk a parsed `Node` tree (from either format) into a [`Document`].
+fn process_document(root: &Node) -> Result<Document, String> {
+    let book_node = find_first_child(root, "book").ok_or_else(|| "No <book> found".to_string())?;
+
+    let mut state = DocState {
+        book_code: get_attr_value(book_node, "code"),
+        current_chapter: String::new(),
+        current_verse: String::new(),
+        current_plain: String::new(),
+        current_styled: String::new(),
+        current_footnotes: Vec::new(),
+        current_crossrefs: Vec::new(),
+        current_subtitle: String::new(),
+        rows: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for child in &root.children {
+        process_node(child, &mut state);
+    }
+
+    sort_rows(&mut state.rows);
+    Ok(Document {
+        book: state.book_code,
+        rows: state.rows,
+        diagnostics: state.diagnostics,
+    })
+}
+
+/// Parse USX XML into a `Node` tree, recording each element's byte span as
+/// it's parsed and flagging any closing tag that doesn't match the element
+/// it closes.
+fn parse_xml_str(xml: &str) -> Result<(Node, Vec<Diagnostic>), String> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(false);
+
+    let mut buf = Vec::new();
+    let mut stack: Vec<Node> = Vec::new();
+    let mut root: Option<Node> = None;
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    loop {
+        let event_start = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let mut attrs = HashMap::new();
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                    let value = attr.unescape_value().unwrap_or_default().to_string();
+                    attrs.insert(key, value);
+                }
+                let node = Node {
+                    node_type: NodeType::Element,
+                    name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+                    attrs,
+                    children: Vec::new(),
+                    text: String::new(),
+                    start: event_start,
+                    end: reader.buffer_position(),
+                };
+                stack.push(node);
+            }
+            Ok(Event::End(e)) => {
+                let closing_name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if let Some(mut node) = stack.pop() {
+                    if node.name != closing_name {
+                        diagnostics.push(
+                            Diagnostic::new(&format!("closing tag </{}> does not match <{}>", closing_name, node.name)).with_label(
+                                Span {
+                                    start: node.start,
+                                    end: reader.buffer_position(),
+                                },
+                                &format!("<{}> opened here", node.name),
+                                true,
+                            ),
+                        );
+                    }
+                    node.end = reader.buffer_position();
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(node);
+                    } else {
+                        root = Some(node);
+                    }
+                }
+            }
+            Ok(Event::Text(e)) => {
+                if let Some(parent) = stack.last_mut() {
+                    let text = e.unescape().unwrap_or_default().to_string();
+                    if !text.is_empty() {
+                        parent.children.push(Node {
+                            node_type: NodeType::Text,
+                            name: String::new(),
+                            attrs: HashMap::new(),
+                            children: Vec::new(),
+                            text,
+                            start: event_start,
+                            end: reader.buffer_position(),
+                        });
+                    }
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                let mut attrs = HashMap::new();
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                    let value = attr.unescape_value().unwrap_or_default().to_string();
+                    attrs.insert(key, value);
+                }
+                let end = reader.buffer_position();
+                let node = Node {
+                    node_type: NodeType::Element,
+                    name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+                    attrs,
+                    children: Vec::new(),
+                    text: String::new(),
+                    start: event_start,
+                    end,
+                };
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(node);
+                } else {
+                    root = Some(node);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(e.to_string()),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let root = root.unwrap_or(Node {
+        node_type: NodeType::Element,
+        name: String::new(),
+        attrs: HashMap::new(),
+        children: Vec::new(),
+        text: String::new(),
+        start: 0,
+        end: 0,
+    });
+    Ok((root, diagnostics))
+}
+
+/// Shared per-node pass used by both USX and USFM trees: tracks the
+/// current chapter/verse/subtitle and emits a [`Row`] whenever a verse
+/// milestone closes.
+fn process_node(node: &Node, state: &mut DocState) {
+    match node.node_type {
+        NodeType::Element => match node.name.as_str() {
+            "chapter" => {
+                state.current_chapter = get_attr_value(node, "number");
+            }
+            "verse" => {
+                let sid = get_attr_value(node, "sid");
+                let eid = get_attr_value(node, "eid");
+                if !sid.is_empty() {
+                    state.current_verse = get_attr_value(node, "number");
+                    state.current_plain.clear();
+                    state.current_styled.clear();
+                    state.current_footnotes.clear();
+                    state.current_crossrefs.clear();
+                } else if !eid.is_empty() {
+                    emit_row(state);
+                    state.current_verse.clear();
+                    state.current_plain.clear();
+                    state.current_styled.clear();
+                    state.current_footnotes.clear();
+                    state.current_crossrefs.clear();
+                }
+            }
+            "note" => {
+                process_note(node, state);
+                return;
+            }
+            "para" => {
+                let style = get_attr_value(node, "style");
+                if is_subtitle_style(&style) {
+                    let subtitle = normalize_whitespace(&inner_text(node));
+                    if !subtitle.is_empty() {
+                        state.current_subtitle = subtitle;
+                    }
+                }
+            }
+            "char" => {
+                let style = get_attr_value(node, "style");
+                if style == "sup" {
+                    return;
+                }
+                let mut tag = String::new();
+                if !style.is_empty() {
+                    tag = get_styled_tag_name(&style);
+                }
+                if !state.current_verse.is_empty() && !tag.is_empty() {
+                    state.current_styled.push_str(&format!("<{}>", tag));
+                }
+                for child in &node.children {
+                    process_node(child, state);
+                }
+                if !state.current_verse.is_empty() && !tag.is_empty() {
+                    state.current_styled.push_str(&format!("</{}>", tag));
+                }
+                return;
+            }
+            _ => {}
+        },
+        NodeType::Text => {
+            if state.current_verse.is_empty() {
+                return;
+            }
+            let text = normalize_whitespace(&node.text);
+            if text.is_empty() {
+                return;
+            }
+            if !state.current_plain.is_empty() {
+                state.current_plain.push(' ');
+                state.current_styled.push(' ');
+            }
+            state.current_plain.push_str(&text);
+            state.current_styled.push_str(&text);
+        }
+    }
+
+    for child in &node.children {
+        process_node(child, state);
+    }
+}
+
+fn emit_row(state: &mut DocState) {
+    let plain = state.current_plain.trim().to_string();
+    let styled = state.current_styled.trim().to_string();
+    let subtitle = state.current_subtitle.trim().to_string();
+
+    if !state.book_code.is_empty()
+        && !state.current_chapter.is_empty()
+        && !state.current_verse.is_empty()
+        && !plain.is_empty()
+    {
+        state.rows.push(Row {
+            book: state.book_code.clone(),
+            chapter: state.current_chapter.clone(),
+            verse: state.current_verse.clone(),
+            text_plain: plain,
+            text_styled: styled,
+            footnotes: join_notes(&state.current_footnotes),
+            crossrefs: join_notes(&state.current_crossrefs),
+            subtitle,
+            book_name: state.book_code.clone(),
+        });
+    }
+}
+
+fn process_note(node: &Node, state: &mut DocState) {
+    let style = get_attr_value(node, "style");
+    let ft = extract_ft_from_note(node);
+    if ft.is_empty() {
+        state.diagnostics.push(
+            Diagnostic::new(&format!("<note style=\"{}\"> has no <char style=\"ft\"> text", style))
+                .with_label(node.span(), "note opened here", true),
+        );
+        return;
+    }
+    if style.starts_with('x') {
+        state.current_crossrefs.push(ft);
+    } else {
+        state.current_footnotes.push(ft);
+    }
+}
+
+fn extract_ft_from_note(node: &Node) -> String {
+    if let Some(ft_node) = find_ft_node(node) {
+        let raw = inner_text(&ft_node);
+        return normalize_whitespace(&raw);
+    }
+    String::new()
+}
+
+fn find_ft_node(node: &Node) -> Option<Node> {
+    if node.node_type == NodeType::Element && node.name == "char" {
+        if get_attr_value(node, "style") == "ft" {
+            return Some(node.clone());
+        }
+    }
+    for child in &node.children {
+        if let Some(found) = find_ft_node(child) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+pub(crate) fn inner_text(node: &Node) -> String {
+    match node.node_type {
+        NodeType::Text => node.text.clone(),
+        NodeType::Element => {
+            let mut out = String::new();
+            for child in &node.children {
+                out.push_str(&inner_text(child));
+            }
+            out
+        }
+    }
+}
+
+fn find_first_child<'a>(node: &'a Node, name: &str) -> Option<&'a Node> {
+    node.children
+        .iter()
+        .find(|child| matches!(child.node_type, NodeType::Element) && child.name == name)
+}
+
+fn get_attr_value(node: &Node, name: &str) -> String {
+    node.attrs.get(name).cloned().unwrap_or_default()
+}
+
+pub(crate) fn normalize_whitespace(text: &str) -> String {
+    if text.trim().is_empty() {
+        return String::new();
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn get_styled_tag_name(style: &str) -> String {
+    match style {
+        "wj" => "wj",
+        "add" => "add",
+        "nd" => "nd",
+        "bdit" => "bdit",
+        "it" => "i",
+        "bd" => "b",
+        _ => "span",
+    }
+    .to_string()
+}
+
+fn is_subtitle_style(style: &str) -> bool {
+    matches!(
+        style,
+        "s" | "s1" | "s2" | "s3" | "sp" | "ms" | "mr" | "mt" | "mt1" | "mt2"
+    )
+}
+
+fn sort_rows(rows: &mut Vec<Row>) {
+    rows.sort_by(|a, b| {
+        let book_cmp = a.book.cmp(&b.book);
+        if book_cmp != std::cmp::Ordering::Equal {
+            return book_cmp;
+        }
+        let ca = a.chapter.parse::<i32>().unwrap_or(0);
+        let cb = b.chapter.parse::<i32>().unwrap_or(0);
+        if ca != cb {
+            return ca.cmp(&cb);
+        }
+        let verse_num = |v: &str| v.chars().take_while(|c| c.is_ascii_digit()).collect::<String>().parse::<i32>().unwrap_or(0);
+        let va = verse_num(&a.verse);
+        let vb = verse_num(&b.verse);
+        if va != vb {
+            return va.cmp(&vb);
+        }
+        a.verse.cmp(&b.verse)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_usx_self_closing_verse_milestone() {
+        let xml = r#"<usx version="3.0">
+            <book code="GEN" style="id">Genesis</book>
+            <chapter number="1" sid="GEN 1"/>
+            <para style="p">
+                <verse number="1" sid="GEN 1:1"/>In the beginning.<verse eid="GEN 1:1"/>
+            </para>
+        </usx>"#;
+
+        let document = parse_usx(xml).unwrap();
+        assert_eq!(document.rows.len(), 1);
+        let row = &document.rows[0];
+        assert_eq!(row.book, "GEN");
+        assert_eq!(row.chapter, "1");
+        assert_eq!(row.verse, "1");
+        assert_eq!(row.text_plain, "In the beginning.");
+    }
+
+    #[test]
+    fn parse_usx_note_becomes_footnote() {
+        let xml = r#"<usx version="3.0">
+            <book code="GEN" style="id">Genesis</book>
+            <chapter number="1"/>
+            <para style="p">
+                <verse number="1" sid="GEN 1:1"/>In the beginning
+                <note style="f"><char style="ft">A note.</char></note>
+                <verse eid="GEN 1:1"/>
+            </para>
+        </usx>"#;
+
+        let document = parse_usx(xml).unwrap();
+        assert_eq!(document.rows.len(), 1);
+        assert_eq!(document.rows[0].footnotes, "A note.");
+    }
+
+    #[test]
+    fn parse_usx_styled_span() {
+        let xml = r#"<usx version="3.0">
+            <book code="GEN" style="id">Genesis</book>
+            <chapter number="1"/>
+            <para style="p">
+                <verse number="1" sid="GEN 1:1"/>The <char style="bd">Word</char> was with God.<verse eid="GEN 1:1"/>
+            </para>
+        </usx>"#;
+
+        let document = parse_usx(xml).unwrap();
+        assert_eq!(document.rows.len(), 1);
+        assert_eq!(document.rows[0].text_styled, "The <b>Word</b> was with God.");
+    }
+
+    #[test]
+    fn parse_usfm_basic_verse() {
+        let usfm = "\\id GEN\n\\c 1\n\\v 1 In the beginning.\n";
+        let document = parse_usfm(usfm, "GEN").unwrap();
+        assert_eq!(document.rows.len(), 1);
+        assert_eq!(document.rows[0].chapter, "1");
+        assert_eq!(document.rows[0].verse, "1");
+        assert_eq!(document.rows[0].text_plain, "In the beginning.");
+    }
+
+    #[test]
+    fn join_and_split_notes_round_trip_through_literal_pipe() {
+        let notes = vec!["Some note | with a pipe inside it.".to_string(), "Second note.".to_string()];
+        let joined = join_notes(&notes);
+        assert_eq!(split_notes(&joined), notes);
+    }
+}
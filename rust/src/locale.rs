@@ -0,0 +1,98 @@
+//! Book-code localization for the `BookName` output column.
+//!
+//! Book codes (`GEN`, `EXO`, `MAT`, ...) are mapped to human-readable names
+//! via embedded TOML locale tables (`locales/<lang>.toml`, `CODE = "Name"`
+//! pairs). An unrecognized `--lang` falls back to English; a code with no
+//! entry in a known language falls back to the code itself.
+
+use crate::Row;
+use std::collections::HashMap;
+
+const EN_TOML: &str = include_str!("../locales/en.toml");
+const ES_TOML: &str = include_str!("../locales/es.toml");
+
+/// Load the locale table for `lang` (e.g. `"en"`, `"es"`), falling back to
+/// English for an unrecognized language.
+pub fn load_table(lang: &str) -> HashMap<String, String> {
+    let text = match lang.to_lowercase().as_str() {
+        "es" => ES_TOML,
+        _ => EN_TOML,
+    };
+    toml::from_str(text).unwrap_or_default()
+}
+
+/// Look up `code`'s localized name in `table`, falling back to the code
+/// itself when it has no entry.
+pub fn book_name(table: &HashMap<String, String>, code: &str) -> String {
+    table.get(&code.to_uppercase()).cloned().unwrap_or_else(|| code.to_string())
+}
+
+/// Fill in each row's `book_name` from `table`, optionally replacing `book`
+/// with the localized name too.
+pub fn localize_rows(rows: &mut [Row], table: &HashMap<String, String>, replace_book: bool) {
+    for row in rows {
+        let name = book_name(table, &row.book);
+        if replace_book {
+            row.book = name.clone();
+        }
+        row.book_name = name;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_row() -> Row {
+        Row {
+            book: "GEN".to_string(),
+            chapter: "1".to_string(),
+            verse: "1".to_string(),
+            text_plain: "In the beginning.".to_string(),
+            text_styled: "In the beginning.".to_string(),
+            footnotes: String::new(),
+            crossrefs: String::new(),
+            subtitle: String::new(),
+            book_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn book_name_falls_back_to_code_when_unknown() {
+        let table = load_table("en");
+        assert_eq!(book_name(&table, "XYZ"), "XYZ");
+    }
+
+    #[test]
+    fn load_table_differs_between_en_and_es() {
+        let en = load_table("en");
+        let es = load_table("es");
+        assert_eq!(book_name(&en, "GEN"), "Genesis");
+        assert_eq!(book_name(&es, "GEN"), "Génesis");
+    }
+
+    #[test]
+    fn load_table_falls_back_to_english_for_unknown_lang() {
+        let fallback = load_table("fr");
+        let en = load_table("en");
+        assert_eq!(book_name(&fallback, "GEN"), book_name(&en, "GEN"));
+    }
+
+    #[test]
+    fn localize_rows_sets_book_name_without_replacing_book_by_default() {
+        let table = load_table("en");
+        let mut rows = vec![sample_row()];
+        localize_rows(&mut rows, &table, false);
+        assert_eq!(rows[0].book, "GEN");
+        assert_eq!(rows[0].book_name, "Genesis");
+    }
+
+    #[test]
+    fn localize_rows_replaces_book_when_requested() {
+        let table = load_table("en");
+        let mut rows = vec![sample_row()];
+        localize_rows(&mut rows, &table, true);
+        assert_eq!(rows[0].book, "Genesis");
+        assert_eq!(rows[0].book_name, "Genesis");
+    }
+}
@@ -0,0 +1,154 @@
+//! Markdown export: render a parsed [`crate::Document`]'s rows as
+//! chapter-per-file Markdown, with footnotes and crossrefs as native
+//! Markdown footnote references rather than flattened CSV cells.
+//!
+//! Styled runs reuse the same tags `get_styled_tag_name` already wrote into
+//! `text_styled`, translated to the closest Markdown emphasis markup;
+//! styles with no Markdown equivalent (`wj`, `add`, `nd`, the `span`
+//! fallback) are simply unwrapped.
+
+use crate::{split_notes, Row};
+
+/// One rendered chapter, ready to write to its own `.md` file.
+pub struct ChapterMarkdown {
+    pub chapter: String,
+    pub markdown: String,
+}
+
+const MD_STYLE_MARKERS: &[(&str, &str)] = &[("bdit", "***"), ("b", "**"), ("i", "*")];
+const UNMAPPED_STYLES: &[&str] = &["wj", "add", "nd", "span"];
+
+/// Render every chapter in `rows` (already sorted book/chapter/verse) into a
+/// standalone Markdown document per chapter.
+pub fn render_chapters(book: &str, rows: &[Row]) -> Vec<ChapterMarkdown> {
+    let mut chapters: Vec<ChapterMarkdown> = Vec::new();
+    let mut current_chapter = String::new();
+    let mut body = String::new();
+    let mut footnotes: Vec<(String, String)> = Vec::new();
+    let mut last_subtitle = String::new();
+
+    for row in rows {
+        if row.chapter != current_chapter {
+            if !current_chapter.is_empty() {
+                chapters.push(finish_chapter(&current_chapter, &body, &footnotes));
+            }
+            current_chapter = row.chapter.clone();
+            body.clear();
+            footnotes.clear();
+            last_subtitle.clear();
+            body.push_str(&format!("## {} {}\n\n", book, current_chapter));
+        }
+
+        if !row.subtitle.is_empty() && row.subtitle != last_subtitle {
+            body.push_str(&format!("### {}\n\n", row.subtitle));
+            last_subtitle = row.subtitle.clone();
+        }
+
+        body.push_str(&format!("<sup>{}</sup> {}", row.verse, render_styled(&row.text_styled)));
+
+        let mut verse_note_idx = 0;
+        for note in split_notes(&row.footnotes) {
+            verse_note_idx += 1;
+            push_footnote(&mut body, &mut footnotes, book, &current_chapter, &row.verse, verse_note_idx, &note, false);
+        }
+        for xref in split_notes(&row.crossrefs) {
+            verse_note_idx += 1;
+            push_footnote(&mut body, &mut footnotes, book, &current_chapter, &row.verse, verse_note_idx, &xref, true);
+        }
+
+        body.push_str("\n\n");
+    }
+
+    if !current_chapter.is_empty() {
+        chapters.push(finish_chapter(&current_chapter, &body, &footnotes));
+    }
+
+    chapters
+}
+
+fn finish_chapter(chapter: &str, body: &str, footnotes: &[(String, String)]) -> ChapterMarkdown {
+    let mut markdown = body.to_string();
+    if !footnotes.is_empty() {
+        markdown.push_str("---\n\n");
+        for (id, text) in footnotes {
+            markdown.push_str(&format!("[^{}]: {}\n", id, text));
+        }
+    }
+    ChapterMarkdown {
+        chapter: chapter.to_string(),
+        markdown,
+    }
+}
+
+fn push_footnote(body: &mut String, footnotes: &mut Vec<(String, String)>, book: &str, chapter: &str, verse: &str, idx: usize, text: &str, is_crossref: bool) {
+    let id = format!("{}-{}-{}-{}", book.to_lowercase(), chapter, verse, idx);
+    body.push_str(&format!("[^{}]", id));
+    let definition = if is_crossref { format!("See also: {}", text) } else { text.to_string() };
+    footnotes.push((id, definition));
+}
+
+/// Translate a `text_styled` string's tags to Markdown emphasis, leaving
+/// styles with no Markdown equivalent unwrapped.
+fn render_styled(styled: &str) -> String {
+    let mut text = styled.to_string();
+    for (tag, marker) in MD_STYLE_MARKERS {
+        text = text.replace(&format!("<{}>", tag), marker).replace(&format!("</{}>", tag), marker);
+    }
+    for tag in UNMAPPED_STYLES {
+        text = text.replace(&format!("<{}>", tag), "").replace(&format!("</{}>", tag), "");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(chapter: &str, verse: &str, text_styled: &str, footnotes: &str, crossrefs: &str) -> Row {
+        Row {
+            book: "GEN".to_string(),
+            chapter: chapter.to_string(),
+            verse: verse.to_string(),
+            text_plain: text_styled.to_string(),
+            text_styled: text_styled.to_string(),
+            footnotes: footnotes.to_string(),
+            crossrefs: crossrefs.to_string(),
+            subtitle: String::new(),
+            book_name: "Genesis".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_styled_maps_known_tags_to_markdown_emphasis() {
+        assert_eq!(render_styled("The <b>Word</b> was <i>good</i>."), "The **Word** was *good*.");
+        assert_eq!(render_styled("He <wj>spoke</wj>."), "He spoke.");
+    }
+
+    #[test]
+    fn footnote_ids_reset_per_verse_not_per_chapter() {
+        let rows = vec![
+            row("1", "1", "In the beginning.", "First note.", ""),
+            row("1", "2", "The earth was formless.", "Second note.", ""),
+        ];
+
+        let chapters = render_chapters("GEN", &rows);
+        assert_eq!(chapters.len(), 1);
+        let markdown = &chapters[0].markdown;
+
+        assert!(markdown.contains("[^gen-1-1-1]"));
+        assert!(markdown.contains("[^gen-1-2-1]"));
+        assert!(!markdown.contains("gen-1-2-2"));
+    }
+
+    #[test]
+    fn footnote_and_crossref_in_same_verse_get_distinct_indices() {
+        let rows = vec![row("1", "1", "In the beginning.", "A footnote.", "A crossref.")];
+        let chapters = render_chapters("GEN", &rows);
+        let markdown = &chapters[0].markdown;
+
+        assert!(markdown.contains("[^gen-1-1-1]"));
+        assert!(markdown.contains("[^gen-1-1-2]"));
+        assert!(markdown.contains("[^gen-1-1-1]: A footnote."));
+        assert!(markdown.contains("[^gen-1-1-2]: See also: A crossref."));
+    }
+}
@@ -0,0 +1,111 @@
+//! Regenerate a USFM document from a previously produced row table.
+//!
+//! This is the inverse of [`crate::parse_usfm`]/[`crate::parse_usx`], but it
+//! is necessarily lossy in one direction: a [`crate::Row`] only remembers
+//! the *final* styled/note text, not the original markup. What comes back
+//! out:
+//!
+//! - Losslessly recoverable: `book`, `chapter`, `verse`, the verse text
+//!   (from `text_styled`, falling back to `text_plain`), `footnotes`, and
+//!   `crossrefs`.
+//! - Approximated: `subtitle` is always re-emitted as a plain `\s` heading
+//!   marker, since the row table does not record which heading level
+//!   (`s1`/`s2`/`mt`/...) produced it. Styled spans are mapped back to the
+//!   USFM marker that *usually* produces that tag (e.g. `<i>` → `\it`), but
+//!   since both USX and USFM can collapse distinct source styles onto the
+//!   same tag (and unknown styles fall back to a bare `<span>`), the
+//!   regenerated markers are a best effort, not a byte-for-byte original.
+
+use crate::{split_notes, Row};
+
+const STYLE_MARKERS: &[(&str, &str)] = &[
+    ("wj", "wj"),
+    ("add", "add"),
+    ("nd", "nd"),
+    ("bdit", "bdit"),
+    ("i", "it"),
+    ("b", "bd"),
+];
+
+/// Render a row table (already sorted into book/chapter/verse order) back
+/// into a `.usfm` document.
+pub fn rows_to_usfm(rows: &[Row]) -> String {
+    let mut out = String::new();
+    let Some(first) = rows.first() else {
+        return out;
+    };
+
+    out.push_str(&format!("\\id {}\n", first.book));
+
+    let mut last_chapter = String::new();
+    let mut last_subtitle = String::new();
+
+    for row in rows {
+        if row.chapter != last_chapter {
+            out.push_str(&format!("\\c {}\n", row.chapter));
+            last_chapter = row.chapter.clone();
+            last_subtitle.clear();
+        }
+
+        if !row.subtitle.is_empty() && row.subtitle != last_subtitle {
+            out.push_str(&format!("\\s {}\n", row.subtitle));
+            last_subtitle = row.subtitle.clone();
+        }
+
+        out.push_str(&format!("\\v {} {}", row.verse, verse_body(row)));
+        for note in split_notes(&row.footnotes) {
+            out.push_str(&format!(" \\f + \\ft {}\\f*", note));
+        }
+        for xref in split_notes(&row.crossrefs) {
+            out.push_str(&format!(" \\x + \\xt {}\\x*", xref));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn verse_body(row: &Row) -> String {
+    let source = if row.text_styled.is_empty() { &row.text_plain } else { &row.text_styled };
+    let mut text = source.clone();
+    for (tag, marker) in STYLE_MARKERS {
+        text = text.replace(&format!("<{}>", tag), &format!("\\{} ", marker));
+        text = text.replace(&format!("</{}>", tag), &format!("\\{}*", marker));
+    }
+    text.replace("<span>", "").replace("</span>", "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_to_usfm_emits_id_chapter_verse_and_note_markers() {
+        let xml = r#"<usx version="3.0">
+            <book code="GEN" style="id">Genesis</book>
+            <chapter number="1" sid="GEN 1"/>
+            <para style="p">
+                <verse number="1" sid="GEN 1:1"/>In the <char style="bd">beginning</char>
+                <note style="f"><char style="ft">A note.</char></note>
+                <verse eid="GEN 1:1"/>
+            </para>
+        </usx>"#;
+
+        let document = crate::parse_usx(xml).unwrap();
+        let usfm_text = rows_to_usfm(&document.rows);
+
+        assert!(usfm_text.starts_with("\\id GEN\n"));
+        assert!(usfm_text.contains("\\c 1\n"));
+        assert!(usfm_text.contains("\\v 1 In the\\bd"));
+        assert!(usfm_text.contains("beginning\\bd*"));
+        assert!(usfm_text.contains("\\f + \\ft A note.\\f*"));
+
+        let reparsed = crate::parse_usfm(&usfm_text, "GEN").unwrap();
+        assert_eq!(reparsed.rows.len(), 1);
+        assert_eq!(reparsed.rows[0].book, "GEN");
+        assert_eq!(reparsed.rows[0].chapter, "1");
+        assert_eq!(reparsed.rows[0].verse, "1");
+        assert_eq!(reparsed.rows[0].footnotes, "A note.");
+        assert!(reparsed.rows[0].text_plain.contains("beginning"));
+    }
+}
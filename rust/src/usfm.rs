@@ -0,0 +1,233 @@
+//! USFM/SFM marker parsing.
+//!
+//! USFM has no angle-bracket nesting, so this module translates its line
+//! markers and inline `\tag ... \tag*` runs into the same `chapter`/`verse`/
+//! `para`/`char`/`note` vocabulary the shared `process_document` pass
+//! already understands for USX, rather than maintaining a second
+//! style/note pass.
+
+use crate::{normalize_whitespace, Node};
+use regex::Regex;
+
+/// A placeholder for an extracted footnote/crossref note, spliced back into
+/// the inline token stream so it lands in the right position relative to
+/// surrounding styled runs. `\x00` can't occur in well-formed USFM text.
+fn note_placeholder(index: usize) -> String {
+    format!("\u{0}NOTE{}\u{0}", index)
+}
+
+const INLINE_TOKEN_PATTERN: &str = r"\x00NOTE(\d+)\x00|\\(\+)?([A-Za-z][A-Za-z0-9]*)(\*)?";
+
+pub(crate) fn build_node_tree(text: &str, fallback_book: &str) -> Node {
+    let normalized = text.replace("\r\n", "\n");
+    let lines: Vec<&str> = normalized.split('\n').collect();
+
+    let mut book_code = fallback_book.to_string();
+    let re_id = Regex::new(r"(?i)^\\id\s+(\S+)").unwrap();
+    for line in &lines {
+        let l = line.trim();
+        if l.is_empty() {
+            continue;
+        }
+        if let Some(caps) = re_id.captures(l) {
+            if let Some(val) = caps.get(1) {
+                book_code = val.as_str().to_string();
+                break;
+            }
+        }
+    }
+
+    let re_chapter = Regex::new(r"(?i)^\\c\s+(\d+)\b").unwrap();
+    let re_heading = Regex::new(r"(?i)^\\(s[0-3]?|sp|ms|mr|mt[12]?)\s*(.*)$").unwrap();
+    let re_verse = Regex::new(r"(?i)^\\v\s+(\d+)\s*(.*)$").unwrap();
+    let re_para = Regex::new(r"(?i)^\\(m|p|pi|q[0-4]?|qt[0-4]?)\s*(.*)$").unwrap();
+
+    let mut children: Vec<Node> = vec![Node::element("book").with_attr("code", &book_code)];
+    let mut chapter_num = String::new();
+    let mut verse_num = String::new();
+    let mut in_verse = false;
+
+    for line in lines {
+        let l = line.trim();
+        if l.is_empty() {
+            continue;
+        }
+
+        if let Some(caps) = re_chapter.captures(l) {
+            close_verse(&mut children, &book_code, &chapter_num, &verse_num, &mut in_verse);
+            chapter_num = caps.get(1).unwrap().as_str().to_string();
+            children.push(Node::element("chapter").with_attr("number", &chapter_num));
+            continue;
+        }
+
+        if let Some(caps) = re_heading.captures(l) {
+            let rest = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+            children.push(Node::element("para").with_attr("style", &caps[1].to_lowercase()).with_children(parse_inline(rest)));
+            continue;
+        }
+
+        if let Some(caps) = re_verse.captures(l) {
+            close_verse(&mut children, &book_code, &chapter_num, &verse_num, &mut in_verse);
+            verse_num = caps.get(1).unwrap().as_str().to_string();
+            let sid = format!("{}.{}.{}", book_code, chapter_num, verse_num);
+            children.push(Node::element("verse").with_attr("sid", &sid).with_attr("number", &verse_num));
+            in_verse = true;
+
+            let rest = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+            children.extend(parse_inline(rest));
+            continue;
+        }
+
+        if let Some(caps) = re_para.captures(l) {
+            let rest = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+            if in_verse {
+                children.extend(parse_inline(rest));
+            }
+            continue;
+        }
+
+        if in_verse {
+            children.extend(parse_inline(l));
+        }
+    }
+
+    close_verse(&mut children, &book_code, &chapter_num, &verse_num, &mut in_verse);
+
+    Node::element("usfm").with_children(children)
+}
+
+fn close_verse(children: &mut Vec<Node>, book: &str, chapter: &str, verse: &str, in_verse: &mut bool) {
+    if *in_verse {
+        let eid = format!("{}.{}.{}", book, chapter, verse);
+        children.push(Node::element("verse").with_attr("eid", &eid));
+        *in_verse = false;
+    }
+}
+
+/// Parse one line's worth of inline USFM content (after its leading
+/// paragraph/verse marker has been stripped) into text/char/note nodes.
+fn parse_inline(segment: &str) -> Vec<Node> {
+    if segment.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let re_sup = Regex::new(r"(?is)\\\+?sup\b.*?\\\+?sup\*").unwrap();
+    let without_sup = re_sup.replace_all(segment, " ").to_string();
+
+    let (spliced, notes) = extract_note_nodes(&without_sup);
+    tokenize_inline(&spliced, &notes)
+}
+
+/// Replace `\f ... \f*` / `\x ... \x*` blocks with placeholders and return
+/// the note `Node`s they represent, indexed by placeholder number.
+fn extract_note_nodes(segment: &str) -> (String, Vec<Node>) {
+    let mut notes: Vec<Node> = Vec::new();
+
+    let re_foot = Regex::new(r"(?is)\\f\b(.*?\\f\*)").unwrap();
+    let after_foot = re_foot
+        .replace_all(segment, |caps: &regex::Captures| {
+            let full = format!("\\f{}", &caps[1]);
+            let placeholder = note_placeholder(notes.len());
+            notes.push(build_note_node("f", &full));
+            placeholder
+        })
+        .to_string();
+
+    let re_cross = Regex::new(r"(?is)\\x\b(.*?\\x\*)").unwrap();
+    let after_cross = re_cross
+        .replace_all(&after_foot, |caps: &regex::Captures| {
+            let full = format!("\\x{}", &caps[1]);
+            let placeholder = note_placeholder(notes.len());
+            notes.push(build_note_node("x", &full));
+            placeholder
+        })
+        .to_string();
+
+    (after_cross, notes)
+}
+
+fn build_note_node(style: &str, full_note_text: &str) -> Node {
+    let ft = extract_ft_from_usfm_note_text(full_note_text);
+    let ft_children = if ft.is_empty() { Vec::new() } else { vec![Node::text(&ft)] };
+    Node::element("note").with_attr("style", style).with_children(vec![
+        Node::element("char").with_attr("style", "ft").with_children(ft_children),
+    ])
+}
+
+fn extract_ft_from_usfm_note_text(note_text: &str) -> String {
+    if note_text.trim().is_empty() {
+        return String::new();
+    }
+
+    let re = Regex::new(r"(?i)\\(?:ft|xt)\b([^\\]*)").unwrap();
+    if let Some(caps) = re.captures(note_text) {
+        if let Some(m) = caps.get(1) {
+            return normalize_whitespace(m.as_str());
+        }
+    }
+
+    String::new()
+}
+
+/// Stack-based scan turning `\tag ... \tag*` runs (and spliced-in note
+/// placeholders) into a tree of `char`/`note`/text nodes.
+fn tokenize_inline(segment: &str, notes: &[Node]) -> Vec<Node> {
+    let re = Regex::new(INLINE_TOKEN_PATTERN).unwrap();
+
+    let mut stack: Vec<(String, Vec<Node>)> = Vec::new();
+    let mut root: Vec<Node> = Vec::new();
+    let mut last_end = 0;
+
+    let push_text = |top: &mut Vec<Node>, text: &str| {
+        if !text.is_empty() {
+            top.push(Node::text(text));
+        }
+    };
+
+    for caps in re.captures_iter(segment) {
+        let m = caps.get(0).unwrap();
+        let plain = &segment[last_end..m.start()];
+        {
+            let top = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut root);
+            push_text(top, plain);
+        }
+        last_end = m.end();
+
+        if let Some(idx) = caps.get(1) {
+            let index: usize = idx.as_str().parse().unwrap_or(0);
+            if let Some(note) = notes.get(index) {
+                let top = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut root);
+                top.push(note.clone());
+            }
+            continue;
+        }
+
+        let name = caps.get(3).unwrap().as_str().to_lowercase();
+        let is_close = caps.get(4).is_some();
+
+        if is_close {
+            if let Some((_, frame_children)) = stack.pop() {
+                let node = Node::element("char").with_attr("style", &name).with_children(frame_children);
+                let top = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut root);
+                top.push(node);
+            }
+        } else {
+            stack.push((name, Vec::new()));
+        }
+    }
+
+    {
+        let top = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut root);
+        push_text(top, &segment[last_end..]);
+    }
+
+    // Any styles left unclosed at the end of this segment (e.g. a style
+    // that closes on a later line) are folded into their parent unwrapped,
+    // rather than carrying an artificial open tag across segments.
+    while let Some((_, frame_children)) = stack.pop() {
+        let top = stack.last_mut().map(|(_, c)| c).unwrap_or(&mut root);
+        top.extend(frame_children);
+    }
+
+    root
+}
@@ -0,0 +1,267 @@
+//! Pluggable output backends for a parsed row table.
+//!
+//! [`Writer`] is the extension point: each backend only needs to know how
+//! to lay `&[Row]` down at a path. [`writer_for`] resolves a `--format`
+//! name to the backend that handles it, so the CLI (and any embedder) can
+//! add a new backend here without touching the parsing code at all.
+
+use crate::Row;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+
+/// A renderer that can lay out a row table in some on-disk format.
+pub trait Writer {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()>;
+
+    /// The file extension this writer's output should use.
+    fn extension(&self) -> &'static str;
+}
+
+/// Resolve a `--format` name (`csv`, `json`, `jsonl`, `cbor`, `sqlite`) to
+/// its [`Writer`].
+pub fn writer_for(format: &str) -> Result<Box<dyn Writer>, String> {
+    match format.to_lowercase().as_str() {
+        "csv" => Ok(Box::new(CsvWriter)),
+        "json" => Ok(Box::new(JsonWriter)),
+        "jsonl" => Ok(Box::new(JsonlWriter)),
+        "cbor" => Ok(Box::new(CborWriter)),
+        "sqlite" => Ok(Box::new(SqliteWriter)),
+        other => Err(format!("Unknown -format '{}': expected csv, json, jsonl, cbor, or sqlite", other)),
+    }
+}
+
+pub struct CsvWriter;
+
+impl Writer for CsvWriter {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record([
+            "Book",
+            "Chapter",
+            "Verse",
+            "TextPlain",
+            "TextStyled",
+            "Footnotes",
+            "Crossrefs",
+            "Subtitle",
+            "BookName",
+        ])?;
+        for row in rows {
+            writer.write_record([
+                &row.book,
+                &row.chapter,
+                &row.verse,
+                &row.text_plain,
+                &row.text_styled,
+                &row.footnotes,
+                &row.crossrefs,
+                &row.subtitle,
+                &row.book_name,
+            ])?;
+        }
+        writer.flush()
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+}
+
+/// A single pretty-printed JSON array of rows.
+pub struct JsonWriter;
+
+impl Writer for JsonWriter {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(rows).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        fs::write(path, json)
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+}
+
+/// One JSON object per row per line, so consumers can stream it.
+pub struct JsonlWriter;
+
+impl Writer for JsonlWriter {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        for row in rows {
+            let line = serde_json::to_string(row).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn extension(&self) -> &'static str {
+        "jsonl"
+    }
+}
+
+/// A compact CBOR array of maps, one per row.
+pub struct CborWriter;
+
+impl Writer for CborWriter {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()> {
+        let file = fs::File::create(path)?;
+        ciborium::into_writer(&rows, file).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+    }
+
+    fn extension(&self) -> &'static str {
+        "cbor"
+    }
+}
+
+/// A single-table SQLite database, one row per verse.
+pub struct SqliteWriter;
+
+impl Writer for SqliteWriter {
+    fn write_rows(&self, path: &Path, rows: &[Row]) -> io::Result<()> {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        let conn = rusqlite::Connection::open(path).map_err(sqlite_err)?;
+        conn.execute_batch(
+            "CREATE TABLE verses (
+                book TEXT NOT NULL,
+                chapter TEXT NOT NULL,
+                verse TEXT NOT NULL,
+                text_plain TEXT NOT NULL,
+                text_styled TEXT NOT NULL,
+                footnotes TEXT NOT NULL,
+                crossrefs TEXT NOT NULL,
+                subtitle TEXT NOT NULL,
+                book_name TEXT NOT NULL
+            );",
+        )
+        .map_err(sqlite_err)?;
+
+        let tx = conn.unchecked_transaction().map_err(sqlite_err)?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO verses (book, chapter, verse, text_plain, text_styled, footnotes, crossrefs, subtitle, book_name)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )
+                .map_err(sqlite_err)?;
+            for row in rows {
+                stmt.execute(rusqlite::params![
+                    row.book,
+                    row.chapter,
+                    row.verse,
+                    row.text_plain,
+                    row.text_styled,
+                    row.footnotes,
+                    row.crossrefs,
+                    row.subtitle,
+                    row.book_name,
+                ])
+                .map_err(sqlite_err)?;
+            }
+        }
+        tx.commit().map_err(sqlite_err)
+    }
+
+    fn extension(&self) -> &'static str {
+        "sqlite"
+    }
+}
+
+fn sqlite_err(e: rusqlite::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_rows() -> Vec<Row> {
+        vec![
+            Row {
+                book: "GEN".to_string(),
+                chapter: "1".to_string(),
+                verse: "1".to_string(),
+                text_plain: "In the beginning.".to_string(),
+                text_styled: "In the beginning.".to_string(),
+                footnotes: String::new(),
+                crossrefs: String::new(),
+                subtitle: String::new(),
+                book_name: "Genesis".to_string(),
+            },
+            Row {
+                book: "GEN".to_string(),
+                chapter: "1".to_string(),
+                verse: "2".to_string(),
+                text_plain: "The earth was formless.".to_string(),
+                text_styled: "The earth was formless.".to_string(),
+                footnotes: "A note.".to_string(),
+                crossrefs: String::new(),
+                subtitle: String::new(),
+                book_name: "Genesis".to_string(),
+            },
+        ]
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("usxtocsv_writers_test_{}_{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn writer_for_dispatches_known_formats() {
+        assert_eq!(writer_for("csv").unwrap().extension(), "csv");
+        assert_eq!(writer_for("JSON").unwrap().extension(), "json");
+        assert_eq!(writer_for("jsonl").unwrap().extension(), "jsonl");
+        assert_eq!(writer_for("cbor").unwrap().extension(), "cbor");
+        assert_eq!(writer_for("sqlite").unwrap().extension(), "sqlite");
+        assert!(writer_for("bogus").is_err());
+    }
+
+    #[test]
+    fn csv_writer_header_and_column_order() {
+        let path = temp_path("rows.csv");
+        CsvWriter.write_rows(&path, &sample_rows()).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        let mut lines = contents.lines();
+        assert_eq!(lines.next().unwrap(), "Book,Chapter,Verse,TextPlain,TextStyled,Footnotes,Crossrefs,Subtitle,BookName");
+        assert_eq!(lines.next().unwrap(), "GEN,1,1,In the beginning.,In the beginning.,,,,Genesis");
+        assert_eq!(lines.next().unwrap(), "GEN,1,2,The earth was formless.,The earth was formless.,A note.,,,Genesis");
+    }
+
+    #[test]
+    fn jsonl_writer_one_object_per_line() {
+        let path = temp_path("rows.jsonl");
+        JsonlWriter.write_rows(&path, &sample_rows()).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"verse\":\"1\""));
+        assert!(lines[1].contains("\"footnotes\":\"A note.\""));
+    }
+
+    #[test]
+    fn sqlite_writer_creates_table_and_rows() {
+        let path = temp_path("rows.sqlite");
+        SqliteWriter.write_rows(&path, &sample_rows()).unwrap();
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM verses", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+
+        let (verse, footnotes): (String, String) = conn
+            .query_row("SELECT verse, footnotes FROM verses WHERE verse = '2'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(verse, "2");
+        assert_eq!(footnotes, "A note.");
+
+        drop(conn);
+        fs::remove_file(&path).ok();
+    }
+}